@@ -0,0 +1,44 @@
+//! Thin wrapper around `tree_sitter` for parsing Fortran source and walking
+//! the resulting concrete syntax tree.
+
+use tree_sitter::{Node, Parser, Tree};
+
+/// Parse Fortran source text into a `tree_sitter` syntax tree.
+pub fn parse(source: &str) -> anyhow::Result<Tree> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&tree_sitter_fortran::LANGUAGE.into())
+        .expect("Error loading Fortran grammar");
+    parser
+        .parse(source, None)
+        .ok_or_else(|| anyhow::anyhow!("Failed to parse source as Fortran"))
+}
+
+/// Iterate over every named descendant of `node`, in depth-first order.
+pub fn named_descendants(node: &Node) -> impl Iterator<Item = Node> {
+    let mut cursor = node.walk();
+    let mut out = Vec::new();
+    let mut reached_root = false;
+    while !reached_root {
+        if cursor.node().is_named() && cursor.node().id() != node.id() {
+            out.push(cursor.node());
+        }
+        if cursor.goto_first_child() {
+            continue;
+        }
+        loop {
+            if cursor.node().id() == node.id() {
+                reached_root = true;
+                break;
+            }
+            if cursor.goto_next_sibling() {
+                break;
+            }
+            if !cursor.goto_parent() {
+                reached_root = true;
+                break;
+            }
+        }
+    }
+    out.into_iter()
+}
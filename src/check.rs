@@ -0,0 +1,388 @@
+//! The `check` subcommand: run every enabled rule over a set of files and
+//! report the violations found, optionally applying their suggested fixes.
+
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::path::Path;
+
+use anyhow::Context;
+
+use crate::ast;
+use crate::cli::{CheckArgs, ColorChoice, OutputFormat};
+use crate::rules::full_ruleset;
+use crate::settings::Settings;
+use crate::{Code, Diagnostic, Edit, Method, Rule, Severity, Violation};
+
+/// Run `check` over the files named in `args`, printing diagnostics in the
+/// requested format. Returns the process exit code.
+pub fn check(args: CheckArgs) -> i32 {
+    let use_color = match args.color {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => std::io::stdout().is_terminal(),
+    };
+    colored::control::set_override(use_color);
+
+    let mut settings = Settings::default();
+    for raw in &args.severity_overrides {
+        match parse_severity_override(raw) {
+            Ok((code, severity)) => {
+                settings.severity_overrides.insert(code, severity);
+            }
+            Err(err) => eprintln!("Ignoring --severity {}: {}", raw, err),
+        }
+    }
+
+    let ruleset = full_ruleset();
+
+    let severities: HashMap<Code, Severity> = ruleset
+        .iter()
+        .map(|(code, rule)| {
+            let severity = settings
+                .severity_overrides
+                .get(code)
+                .copied()
+                .or_else(|| rule.severity())
+                .unwrap_or_else(|| Severity::default_for_category(code.category));
+            (*code, severity)
+        })
+        .collect();
+
+    let mut files = crate::files::Files::new();
+    let mut diagnostics = Vec::new();
+    for path in &args.files {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("Failed to read {}: {}", path.display(), err);
+                continue;
+            }
+        };
+
+        let violations: Vec<(Code, Severity, Violation)> =
+            run_rules(path, &source, &ruleset, &settings)
+                .into_iter()
+                .map(|(code, violation)| (code, severities[&code], violation))
+                .collect();
+
+        let remaining = if args.fix {
+            let fixable: std::collections::HashSet<Code> = ruleset
+                .iter()
+                .filter(|(_, rule)| rule.fixable())
+                .map(|(code, _)| *code)
+                .collect();
+            let (fixed_source, fixed, left) = apply_fixes(&source, violations, &fixable);
+            if fixed > 0 {
+                if let Err(err) = std::fs::write(path, fixed_source) {
+                    eprintln!("Failed to write {}: {}", path.display(), err);
+                }
+            }
+            println!(
+                "{}: fixed {} violation(s), {} left",
+                path.display(),
+                fixed,
+                left.len()
+            );
+            left
+        } else {
+            violations
+        };
+
+        diagnostics.extend(remaining.into_iter().map(|(code, severity, violation)| {
+            Diagnostic::new(path, code, severity, &violation)
+        }));
+
+        // Seed the cache with the text these diagnostics' positions were
+        // actually computed against, so rendering below can't pick up a file
+        // that `--fix` has since rewritten on disk.
+        files.insert(path.clone(), source);
+    }
+    diagnostics.sort();
+
+    match args.output_format {
+        OutputFormat::Text => {
+            let mut explained = std::collections::HashSet::new();
+            for diagnostic in &diagnostics {
+                match diagnostic.render(&mut files, use_color) {
+                    Ok(rendered) => print!("{}", rendered),
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        print!("{}", diagnostic);
+                    }
+                }
+                if args.explain && explained.insert(diagnostic.code()) {
+                    if let Some((_, rule)) = ruleset.iter().find(|(c, _)| *c == diagnostic.code())
+                    {
+                        println!("{}", rule.explain());
+                        println!();
+                    }
+                }
+            }
+        }
+        OutputFormat::Json => match serde_json::to_string_pretty(&diagnostics) {
+            Ok(json) => println!("{}", json),
+            Err(err) => {
+                eprintln!("Failed to serialize diagnostics: {}", err);
+                return 1;
+            }
+        },
+    }
+
+    i32::from(
+        diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity() == Severity::Error),
+    )
+}
+
+/// Parse a single `--severity` argument of the form `CODE=LEVEL`, e.g.
+/// `S001=error`, into the code it overrides and the severity to use instead.
+fn parse_severity_override(raw: &str) -> anyhow::Result<(Code, Severity)> {
+    let (code_str, severity_str) = raw
+        .split_once('=')
+        .context(format!("expected CODE=LEVEL, got {}", raw))?;
+    let code = Code::from(code_str)?;
+    let severity = severity_str.parse::<Severity>()?;
+    Ok((code, severity))
+}
+
+/// Run every rule in `ruleset` over `source`, parsing it into a syntax tree at
+/// most once. Tree-sitter rules are grouped by the node kinds in their
+/// `entrypoints()`, so a single walk over `named_descendants` dispatches each
+/// visited node to every rule registered for that kind, instead of each rule
+/// re-parsing and re-walking the file on its own. `Path` and `Text` rules keep
+/// their existing single-pass model, running once each over `path` and
+/// `source` respectively.
+fn run_rules(
+    path: &Path,
+    source: &str,
+    ruleset: &[(Code, Box<dyn Rule>)],
+    settings: &Settings,
+) -> Vec<(Code, Violation)> {
+    let mut violations = Vec::new();
+
+    for (code, rule) in ruleset {
+        if let Method::Path(f) = rule.method() {
+            if let Some(violation) = f(path) {
+                violations.push((*code, violation));
+            }
+        }
+    }
+
+    let mut tree_rules: HashMap<&str, Vec<(Code, &dyn Rule)>> = HashMap::new();
+    for (code, rule) in ruleset {
+        if matches!(rule.method(), Method::Tree(_)) {
+            for entrypoint in rule.entrypoints() {
+                tree_rules
+                    .entry(entrypoint)
+                    .or_default()
+                    .push((*code, rule.as_ref()));
+            }
+        }
+    }
+
+    if !tree_rules.is_empty() {
+        match ast::parse(source) {
+            Ok(tree) => {
+                for node in ast::named_descendants(&tree.root_node()) {
+                    let Some(rules) = tree_rules.get(node.kind()) else {
+                        continue;
+                    };
+                    for (code, rule) in rules {
+                        if let Method::Tree(f) = rule.method() {
+                            if let Some(violation) = f(&node, source) {
+                                violations.push((*code, violation));
+                            }
+                        }
+                    }
+                }
+            }
+            Err(err) => eprintln!("Failed to parse source: {}", err),
+        }
+    }
+
+    for (code, rule) in ruleset {
+        if let Method::Text(f) = rule.method() {
+            violations.extend(f(source, settings).into_iter().map(|v| (*code, v)));
+        }
+    }
+
+    violations
+}
+
+/// Split `violations` into those whose fix was applied to `source` and those left
+/// over (either unfixable, not opted into `fixable`, or overlapping a fix
+/// already accepted this pass), and return the rewritten source alongside the
+/// count of violations fixed.
+///
+/// Fixes are accepted or deferred as a whole: a multi-edit `Fix` is only applied
+/// if none of its edits overlap an edit from an already-accepted fix, so a file
+/// is never rewritten with half of one violation's fix applied. Overlapping
+/// fixes are resolved by taking the one whose fix starts earliest in the file
+/// and deferring the rest to a subsequent `--fix` pass, mirroring how `rustfix`
+/// iterates fixes to a fixpoint across multiple runs. Only codes present in
+/// `fixable` (i.e. whose rule returns `true` from [`crate::Rule::fixable`])
+/// are eligible at all, even if their violation happens to carry a `Fix`.
+fn apply_fixes(
+    source: &str,
+    violations: Vec<(Code, Severity, Violation)>,
+    fixable: &std::collections::HashSet<Code>,
+) -> (String, usize, Vec<(Code, Severity, Violation)>) {
+    let mut ordered = violations;
+    ordered.sort_by_key(|(_, _, violation)| {
+        violation
+            .fix()
+            .and_then(|fix| fix.edits().iter().map(|edit| edit.start_byte).min())
+            .unwrap_or(usize::MAX)
+    });
+
+    let mut accepted_edits: Vec<Edit> = Vec::new();
+    let mut left = Vec::new();
+    let mut fixed = 0;
+
+    for (code, severity, violation) in ordered {
+        let Some(fix) = violation.fix().filter(|_| fixable.contains(&code)) else {
+            left.push((code, severity, violation));
+            continue;
+        };
+        let overlaps = fix.edits().iter().any(|edit| {
+            accepted_edits
+                .iter()
+                .any(|accepted| edit.start_byte < accepted.end_byte && accepted.start_byte < edit.end_byte)
+        });
+        if overlaps {
+            left.push((code, severity, violation));
+        } else {
+            accepted_edits.extend(fix.edits().iter().cloned());
+            fixed += 1;
+        }
+    }
+    accepted_edits.sort_by_key(|edit| edit.start_byte);
+
+    let mut rewritten = String::with_capacity(source.len());
+    let mut cursor = 0;
+    for edit in &accepted_edits {
+        rewritten.push_str(&source[cursor..edit.start_byte]);
+        rewritten.push_str(&edit.replacement);
+        cursor = edit.end_byte;
+    }
+    rewritten.push_str(&source[cursor..]);
+
+    (rewritten, fixed, left)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Category, Fix, ViolationPosition};
+
+    #[test]
+    fn apply_fixes_defers_whole_overlapping_fix() {
+        let source = "abcdef".to_string();
+        let code = Code::new(Category::Style, 1);
+        let severity = Severity::Warning;
+
+        // Two fixes both touch byte 2: the first should be applied in full, and
+        // the second deferred in full, not partially applied.
+        let first = Violation::new("first", ViolationPosition::Line(1))
+            .with_fix(Fix::single(0, 3, "XXX"));
+        let second = Violation::new("second", ViolationPosition::Line(1))
+            .with_fix(Fix::new(vec![
+                Edit {
+                    start_byte: 2,
+                    end_byte: 4,
+                    replacement: "YY".to_string(),
+                },
+                Edit {
+                    start_byte: 4,
+                    end_byte: 6,
+                    replacement: "ZZ".to_string(),
+                },
+            ]));
+
+        let (rewritten, fixed, left) = apply_fixes(
+            &source,
+            vec![(code, severity, first), (code, severity, second)],
+            &std::collections::HashSet::from([code]),
+        );
+
+        assert_eq!(fixed, 1);
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].2.message(), "second");
+        // The second fix's edits must not appear at all, not just partially.
+        assert_eq!(rewritten, "XXXdef");
+    }
+
+    #[test]
+    fn apply_fixes_skips_codes_not_marked_fixable() {
+        let source = "abcdef".to_string();
+        let code = Code::new(Category::Style, 1);
+        let severity = Severity::Warning;
+
+        let violation = Violation::new("trailing", ViolationPosition::Line(1))
+            .with_fix(Fix::single(0, 3, "XXX"));
+
+        // `code` carries a `Fix`, but is absent from the fixable set, as it
+        // would be for a rule whose `Rule::fixable` returns `false`.
+        let (rewritten, fixed, left) = apply_fixes(
+            &source,
+            vec![(code, severity, violation)],
+            &std::collections::HashSet::new(),
+        );
+
+        assert_eq!(fixed, 0);
+        assert_eq!(left.len(), 1);
+        assert_eq!(rewritten, source);
+    }
+
+    /// A rule that fires once per `name` node, used below to confirm that
+    /// `run_rules` actually reaches a tree rule through its dispatch map
+    /// instead of just never visiting it.
+    struct FlagEveryName;
+
+    impl Rule for FlagEveryName {
+        fn title(&self) -> &str {
+            "flag-every-name"
+        }
+
+        fn method(&self) -> Method {
+            Method::Tree(|node, _source| {
+                Some(Violation::new("found a name", ViolationPosition::Line(
+                    node.start_position().row + 1,
+                )))
+            })
+        }
+
+        fn explain(&self) -> &str {
+            "test-only rule"
+        }
+
+        fn entrypoints(&self) -> Vec<&str> {
+            vec!["name"]
+        }
+    }
+
+    #[test]
+    fn run_rules_dispatches_tree_rule_by_node_kind() {
+        let source = "program test\nend program test\n".to_string();
+        let ruleset: Vec<(Code, Box<dyn Rule>)> = vec![(
+            Code::new(Category::Style, 99),
+            Box::new(FlagEveryName) as Box<dyn Rule>,
+        )];
+
+        let violations = run_rules(
+            Path::new("test.f90"),
+            &source,
+            &ruleset,
+            &Settings::default(),
+        );
+
+        assert!(
+            !violations.is_empty(),
+            "expected at least one `name` node to be visited and flagged"
+        );
+        assert!(violations
+            .iter()
+            .all(|(_, violation)| violation.message() == "found a name"));
+    }
+}
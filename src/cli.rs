@@ -0,0 +1,81 @@
+//! Command line argument parsing.
+
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand, ValueEnum};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: SubCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SubCommands {
+    /// Check files for violations of Fortran best practices.
+    Check(CheckArgs),
+    /// Print the full explanation of one or more rules.
+    Explain(ExplainArgs),
+}
+
+/// The format in which `check` should report diagnostics.
+#[derive(ValueEnum, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable text, with source snippets.
+    #[default]
+    Text,
+    /// A single JSON array, one object per diagnostic.
+    Json,
+}
+
+/// Whether diagnostics should be colorized, following rustc's `ColorConfig`.
+#[derive(ValueEnum, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Colorize when stdout is a terminal, and not otherwise.
+    #[default]
+    Auto,
+    /// Always colorize, even when stdout is piped.
+    Always,
+    /// Never colorize.
+    Never,
+}
+
+#[derive(Parser, Debug)]
+pub struct CheckArgs {
+    /// Files to check. Each path is read as a single source file; directories
+    /// are not searched.
+    pub files: Vec<PathBuf>,
+
+    /// The format in which violations should be reported.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output_format: OutputFormat,
+
+    /// Automatically apply fixes for violations that support them.
+    #[arg(long)]
+    pub fix: bool,
+
+    /// Print each rule's full explanation once, right after the first
+    /// diagnostic it raised. Only applies to text output.
+    #[arg(long)]
+    pub explain: bool,
+
+    /// Whether to colorize output.
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// Override a rule's severity, e.g. `--severity S001=error`. May be given
+    /// more than once.
+    #[arg(long = "severity", value_name = "CODE=LEVEL")]
+    pub severity_overrides: Vec<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct ExplainArgs {
+    /// One or more rule codes to explain, e.g. `S120`.
+    pub codes: Vec<String>,
+}
+
+pub fn parse_args() -> Args {
+    Args::parse()
+}
@@ -0,0 +1,38 @@
+//! The `explain` subcommand: print the long-form rationale behind one or more
+//! rules, looked up by their [`Code`].
+
+use crate::cli::ExplainArgs;
+use crate::rules::full_ruleset;
+use crate::Code;
+
+/// Print the category, title and full explanation for each requested rule code.
+/// Returns the process exit code.
+pub fn explain(args: ExplainArgs) -> i32 {
+    let ruleset = full_ruleset();
+    let mut exit_code = 0;
+
+    for code_str in &args.codes {
+        let code = match Code::from(code_str) {
+            Ok(code) => code,
+            Err(err) => {
+                eprintln!("{}", err);
+                exit_code = 1;
+                continue;
+            }
+        };
+        match ruleset.iter().find(|(c, _)| *c == code) {
+            Some((code, rule)) => {
+                println!("{} ({}): {}", code, code.category, rule.title());
+                println!();
+                println!("{}", rule.explain());
+                println!();
+            }
+            None => {
+                eprintln!("{} is not a known rule code.", code);
+                exit_code = 1;
+            }
+        }
+    }
+
+    exit_code
+}
@@ -0,0 +1,127 @@
+//! A small `codespan`-style files database: a cache mapping each file we've
+//! reported a diagnostic against to its contents and precomputed line-start
+//! byte offsets, so line/column <-> byte-offset conversion is correct and is
+//! computed once per file rather than once per violation.
+
+use anyhow::Context;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// A single file's contents, plus the byte offset at which every line starts.
+pub struct File {
+    contents: String,
+    line_starts: Vec<usize>,
+}
+
+impl File {
+    fn new(contents: String) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(contents.match_indices('\n').map(|(i, _)| i + 1));
+        Self {
+            contents,
+            line_starts,
+        }
+    }
+
+    pub fn contents(&self) -> &str {
+        &self.contents
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// The byte range of 1-indexed `line`, including its trailing newline.
+    pub fn line_range(&self, line: usize) -> Range<usize> {
+        let start = self
+            .line_starts
+            .get(line.saturating_sub(1))
+            .copied()
+            .unwrap_or(self.contents.len());
+        let end = self
+            .line_starts
+            .get(line)
+            .copied()
+            .unwrap_or(self.contents.len());
+        start..end
+    }
+
+    /// Convert a 1-indexed `(line, column)` pair into a byte offset into `contents`.
+    pub fn byte_offset(&self, line: usize, column: usize) -> usize {
+        let line_range = self.line_range(line);
+        // `line_range.end` includes the line's trailing newline, so clamping
+        // to it would let an over-length column spill onto the next line.
+        // Clamp to the line's content instead.
+        let content_end = self.contents[line_range.clone()]
+            .trim_end_matches(['\n', '\r'])
+            .len()
+            + line_range.start;
+        (line_range.start + column.saturating_sub(1)).min(content_end)
+    }
+}
+
+/// Cache of loaded file contents, keyed by path. Diagnostics against the same
+/// file share one entry instead of each re-reading and re-indexing it.
+#[derive(Default)]
+pub struct Files {
+    cache: HashMap<PathBuf, File>,
+}
+
+impl Files {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fetch the cached `File` for `path`, reading and indexing it on first access.
+    pub fn get(&mut self, path: &Path) -> anyhow::Result<&File> {
+        if !self.cache.contains_key(path) {
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            self.cache.insert(path.to_path_buf(), File::new(contents));
+        }
+        Ok(self
+            .cache
+            .get(path)
+            .expect("entry was just inserted if missing"))
+    }
+
+    /// Seed the cache for `path` with `contents` already in hand, so a later
+    /// `get` renders against this exact text instead of re-reading `path` from
+    /// disk — which may since have been rewritten, e.g. by `--fix`.
+    pub fn insert(&mut self, path: PathBuf, contents: String) {
+        self.cache.insert(path, File::new(contents));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_offset_round_trips_across_lines_without_trailing_newline() {
+        // Three lines; the last has no trailing newline, which is the case
+        // `line_starts` has to get right without an extra sentinel entry.
+        let file = File::new("abc\nde\nfgh".to_string());
+
+        assert_eq!(file.line_count(), 3);
+        assert_eq!(file.line_range(1), 0..4);
+        assert_eq!(file.line_range(2), 4..7);
+        assert_eq!(file.line_range(3), 7..10);
+
+        // (line, column) -> byte offset, checked against the known contents.
+        assert_eq!(file.byte_offset(1, 1), 0);
+        assert_eq!(file.byte_offset(2, 1), 4);
+        assert_eq!(file.byte_offset(2, 2), 5);
+        assert_eq!(file.byte_offset(3, 3), 9);
+
+        // Columns past the end of a line clamp to the line's end rather than
+        // spilling into the next line.
+        assert_eq!(file.byte_offset(3, 100), file.contents().len());
+
+        // Same check on a middle line, where `line_range.end` is the start of
+        // the *next* line (just past the '\n'), not the line's own content
+        // end — the clamp must land on the latter.
+        assert_eq!(file.byte_offset(2, 100), 6);
+    }
+}
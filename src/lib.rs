@@ -2,13 +2,16 @@ mod ast;
 pub mod check;
 pub mod cli;
 pub mod explain;
+pub mod files;
 mod rules;
 mod settings;
 use annotate_snippets::{Level, Renderer, Snippet};
 use anyhow::Context;
 use ast::{named_descendants, parse};
-use colored::{ColoredString, Colorize};
+use colored::Colorize;
 use lazy_regex::regex_captures;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use settings::Settings;
 use std::cmp::Ordering;
 use std::fmt;
@@ -67,6 +70,66 @@ impl fmt::Display for Category {
     }
 }
 
+impl Serialize for Category {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// How seriously a violation should be treated: whether it's rendered as an
+/// error, a warning or merely informational, and whether it causes `check`
+/// to exit non-zero.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// The severity a rule is treated as by default, absent a rule- or
+    /// user-level override: parse failures are hard errors, everything else
+    /// is a warning.
+    pub fn default_for_category(category: Category) -> Self {
+        match category {
+            Category::Error => Severity::Error,
+            Category::Style
+            | Category::Typing
+            | Category::Modules
+            | Category::Precision
+            | Category::FileSystem => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "info" => Ok(Self::Info),
+            "warning" => Ok(Self::Warning),
+            "error" => Ok(Self::Error),
+            _ => anyhow::bail!("{} is not a valid severity (expected info, warning or error)", s),
+        }
+    }
+}
+
 /// The combination of a rule category and a unique identifying number.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub struct Code {
@@ -105,6 +168,56 @@ pub enum ViolationPosition {
     LineCol((usize, usize)),
 }
 
+/// On-the-wire representation of a [`ViolationPosition`], shared by the
+/// `Serialize` and `Deserialize` impls so the two stay in lockstep.
+#[derive(Serialize, Deserialize)]
+struct RawPosition {
+    line: Option<usize>,
+    column: Option<usize>,
+}
+
+impl From<ViolationPosition> for RawPosition {
+    fn from(position: ViolationPosition) -> Self {
+        match position {
+            ViolationPosition::None => RawPosition {
+                line: None,
+                column: None,
+            },
+            ViolationPosition::Line(line) => RawPosition {
+                line: Some(line),
+                column: None,
+            },
+            ViolationPosition::LineCol((line, column)) => RawPosition {
+                line: Some(line),
+                column: Some(column),
+            },
+        }
+    }
+}
+
+impl Serialize for ViolationPosition {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        RawPosition::from(*self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ViolationPosition {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = RawPosition::deserialize(deserializer)?;
+        Ok(match (raw.line, raw.column) {
+            (None, _) => ViolationPosition::None,
+            (Some(line), None) => ViolationPosition::Line(line),
+            (Some(line), Some(column)) => ViolationPosition::LineCol((line, column)),
+        })
+    }
+}
+
 // This type is created when a rule is broken. As not all broken rules come with a
 // line number or column, it is recommended to use the `violation!` macro to create
 // these, or the `from_node()` function when creating them from `tree_sitter` queries.
@@ -114,6 +227,13 @@ pub struct Violation {
     message: String,
     /// The location at which the error was detected.
     position: ViolationPosition,
+    /// A machine-applicable suggestion for correcting the violation, if one exists.
+    fix: Option<Fix>,
+    /// Secondary "related location" spans, each with its own label, e.g. to point
+    /// at a conflicting earlier declaration.
+    related: Vec<(ViolationPosition, String)>,
+    /// Free-form notes or help text to print alongside the violation.
+    notes: Vec<String>,
 }
 
 impl Violation {
@@ -121,6 +241,9 @@ impl Violation {
         Self {
             message: String::from(message.as_ref()),
             position,
+            fix: None,
+            related: Vec::new(),
+            notes: Vec::new(),
         }
     }
 
@@ -132,6 +255,29 @@ impl Violation {
         )
     }
 
+    /// Attach a suggested fix to this violation.
+    #[must_use]
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+
+    /// Point at an additional, secondary location relevant to this violation,
+    /// such as where a conflicting name was first declared.
+    #[must_use]
+    pub fn with_related<T: AsRef<str>>(mut self, position: ViolationPosition, label: T) -> Self {
+        self.related
+            .push((position, String::from(label.as_ref())));
+        self
+    }
+
+    /// Attach a free-form note or suggestion to print alongside the violation.
+    #[must_use]
+    pub fn with_note<T: AsRef<str>>(mut self, note: T) -> Self {
+        self.notes.push(String::from(note.as_ref()));
+        self
+    }
+
     pub fn message(&self) -> &str {
         self.message.as_str()
     }
@@ -139,6 +285,57 @@ impl Violation {
     pub fn position(&self) -> ViolationPosition {
         self.position
     }
+
+    pub fn fix(&self) -> Option<&Fix> {
+        self.fix.as_ref()
+    }
+
+    pub fn related(&self) -> &[(ViolationPosition, String)] {
+        &self.related
+    }
+
+    pub fn notes(&self) -> &[String] {
+        &self.notes
+    }
+}
+
+// Fix type
+// --------
+
+/// A single replacement of the source text in the byte range `start_byte..end_byte`
+/// with `replacement`.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Edit {
+    pub start_byte: usize,
+    pub end_byte: usize,
+    pub replacement: String,
+}
+
+/// A machine-applicable suggestion for fixing a [`Violation`], made up of one or
+/// more non-overlapping [`Edit`]s, analogous to the suggestions rustc's diagnostics
+/// carry alongside a lint.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Fix {
+    edits: Vec<Edit>,
+}
+
+impl Fix {
+    pub fn new(edits: Vec<Edit>) -> Self {
+        Self { edits }
+    }
+
+    /// A fix made up of a single edit.
+    pub fn single(start_byte: usize, end_byte: usize, replacement: impl Into<String>) -> Self {
+        Self::new(vec![Edit {
+            start_byte,
+            end_byte,
+            replacement: replacement.into(),
+        }])
+    }
+
+    pub fn edits(&self) -> &[Edit] {
+        &self.edits
+    }
 }
 
 #[macro_export]
@@ -177,6 +374,9 @@ pub trait Rule {
     /// Return a function pointer to the method associated with this rule.
     fn method(&self) -> Method;
 
+    /// Return a short, human-readable title for the rule, e.g. `"trailing-whitespace"`.
+    fn title(&self) -> &str;
+
     /// Return text explaining what the rule tests for, why this is important, and how the user
     /// might fix it.
     fn explain(&self) -> &str;
@@ -186,6 +386,20 @@ pub trait Rule {
     /// should return a vector containing only "TEXT".
     fn entrypoints(&self) -> Vec<&str>;
 
+    /// Override the severity this rule's violations are reported at. Return `None`
+    /// (the default) to fall back to [`Severity::default_for_category`] for the
+    /// rule's category.
+    fn severity(&self) -> Option<Severity> {
+        None
+    }
+
+    /// Whether this rule can suggest a [`Fix`] for (at least some of) the violations
+    /// it raises. Rules that override this to return `true` are expected to attach
+    /// a fix via [`Violation::with_fix`] wherever a correction can be made safely.
+    fn fixable(&self) -> bool {
+        false
+    }
+
     /// Apply a rule over some text, generating all violations raised as a result.
     fn apply(&self, source: &str, settings: &Settings) -> anyhow::Result<Vec<Violation>> {
         match self.method() {
@@ -212,36 +426,61 @@ pub trait Rule {
 pub struct Diagnostic {
     /// The file where an error was reported.
     path: PathBuf,
-    /// The rule code that was violated, expressed as a string.
-    code: String,
+    /// The rule code that was violated.
+    code: Code,
+    /// How seriously this particular diagnostic should be treated.
+    severity: Severity,
     /// The specific violation detected
     violation: Violation,
 }
 
 impl Diagnostic {
-    pub fn new<P, S>(path: P, code: S, violation: &Violation) -> Self
+    pub fn new<P>(path: P, code: Code, severity: Severity, violation: &Violation) -> Self
     where
         P: AsRef<Path>,
-        S: AsRef<str>,
     {
         Self {
             path: path.as_ref().to_path_buf(),
-            code: code.as_ref().to_string(),
+            code,
+            severity,
             violation: violation.clone(),
         }
     }
 
-    fn orderable(&self) -> (&Path, usize, usize, &str) {
+    pub fn code(&self) -> Code {
+        self.code
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn orderable(&self) -> (&Path, usize, usize, Code) {
         match self.violation.position() {
-            ViolationPosition::None => (self.path.as_path(), 0, 0, self.code.as_str()),
-            ViolationPosition::Line(line) => (self.path.as_path(), line, 0, self.code.as_str()),
-            ViolationPosition::LineCol((line, col)) => {
-                (self.path.as_path(), line, col, self.code.as_str())
-            }
+            ViolationPosition::None => (self.path.as_path(), 0, 0, self.code),
+            ViolationPosition::Line(line) => (self.path.as_path(), line, 0, self.code),
+            ViolationPosition::LineCol((line, col)) => (self.path.as_path(), line, col, self.code),
         }
     }
 }
 
+impl Serialize for Diagnostic {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Diagnostic", 7)?;
+        state.serialize_field("path", &self.path)?;
+        state.serialize_field("code", &self.code.to_string())?;
+        state.serialize_field("category", &self.code.category)?;
+        state.serialize_field("number", &self.code.number)?;
+        state.serialize_field("severity", &self.severity.to_string())?;
+        state.serialize_field("message", self.violation.message())?;
+        state.serialize_field("location", &self.violation.position())?;
+        state.end()
+    }
+}
+
 impl Ord for Diagnostic {
     fn cmp(&self, other: &Self) -> Ordering {
         self.orderable().cmp(&other.orderable())
@@ -261,99 +500,128 @@ impl PartialEq for Diagnostic {
 }
 
 impl fmt::Display for Diagnostic {
+    /// A simple, single-line rendering with no source snippet. Prefer
+    /// [`Diagnostic::render`] when a [`files::Files`] cache is available: it
+    /// produces a full, possibly multi-span snippet without re-reading the
+    /// file on every call.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let path = self.path.to_string_lossy().bold();
-        let code = self.code.bold().bright_red();
+        let code = self.code.to_string().bold().bright_red();
         let message = self.violation.message();
         match self.violation.position() {
-            ViolationPosition::None => {
-                write!(f, "{}: {} {}", path, code, message)
-            }
-            ViolationPosition::Line(line) => {
-                format_violation_line_col(self, f, line, 0, message, &path, &code)
-            }
+            ViolationPosition::None => write!(f, "{}: {} {}", path, code, message),
+            ViolationPosition::Line(line) => write!(f, "{}:{}: {} {}", path, line, code, message),
             ViolationPosition::LineCol((line, col)) => {
-                format_violation_line_col(self, f, line, col, message, &path, &code)
+                write!(f, "{}:{}:{}: {} {}", path, line, col, code, message)
             }
         }
     }
 }
 
-/// Read filename into vec of strings
-fn read_lines(filename: &PathBuf) -> Vec<String> {
-    std::fs::read_to_string(filename)
-        .unwrap() // panic on possible file-reading errors
-        .lines() // split the string into an iterator of string slices
-        .map(String::from) // make each slice into a string
-        .collect() // gather them together into a vector
-}
-
-fn format_violation_line_col(
-    diagnostic: &Diagnostic,
-    f: &mut fmt::Formatter,
-    line: usize,
-    col: usize,
-    message: &str,
-    path: &ColoredString,
-    code: &ColoredString,
-) -> fmt::Result {
-    let lines = read_lines(&diagnostic.path);
-    let mut start_index = line.saturating_sub(2).max(1);
-
-    // Trim leading empty lines.
-    while start_index < line {
-        if !lines[start_index.saturating_sub(1)].trim().is_empty() {
-            break;
+impl Diagnostic {
+    /// Render this diagnostic as a codespan-style snippet: the primary span plus
+    /// any secondary "related location" spans and notes, laid out together
+    /// against source pulled from `cache`. `color` chooses between the styled
+    /// and the plain-text `annotate_snippets` renderer; the `colored` crate's
+    /// own output is controlled separately via `colored::control`.
+    pub fn render(&self, cache: &mut files::Files, color: bool) -> anyhow::Result<String> {
+        let path = self.path.to_string_lossy().bold();
+        let code = self.code.to_string().bold().bright_red();
+        let level = annotate_level(self.severity);
+
+        if self.violation.position() == ViolationPosition::None {
+            return Ok(format!("{}: {} {}\n", path, code, self.violation.message()));
         }
-        start_index = start_index.saturating_add(1);
-    }
 
-    let mut end_index = line.saturating_add(2).min(lines.len());
+        // Every labeled span this violation points at: its own primary span,
+        // followed by any secondary "related location" spans.
+        let mut labels: Vec<(ViolationPosition, &str, bool)> =
+            vec![(self.violation.position(), self.violation.message(), true)];
+        labels.extend(
+            self.violation
+                .related()
+                .iter()
+                .map(|(position, label)| (*position, label.as_str(), false)),
+        );
+
+        let file = cache.get(&self.path)?;
+        let lines: Vec<usize> = labels
+            .iter()
+            .filter_map(|(position, ..)| match position {
+                ViolationPosition::Line(line) | ViolationPosition::LineCol((line, _)) => {
+                    Some(*line)
+                }
+                ViolationPosition::None => None,
+            })
+            .collect();
+        let start_line = lines
+            .iter()
+            .copied()
+            .min()
+            .unwrap_or(1)
+            .saturating_sub(2)
+            .max(1);
+        let end_line = lines
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or(1)
+            .saturating_add(2)
+            .min(file.line_count());
+
+        let slice_start = file.line_range(start_line).start;
+        let slice_end = file.line_range(end_line).end;
+        let content_slice = &file.contents()[slice_start..slice_end];
+
+        let mut primary = (start_line, 1);
+        let mut snippet = Snippet::source(content_slice).line_start(start_line);
+        for (position, label, is_primary) in &labels {
+            let (line, col) = match position {
+                ViolationPosition::Line(line) => (*line, 1),
+                ViolationPosition::LineCol((line, col)) => (*line, *col),
+                ViolationPosition::None => continue,
+            };
+            if *is_primary {
+                primary = (line, col);
+            }
+            let offset = file.byte_offset(line, col) - slice_start;
+            let span_level = if *is_primary { level } else { Level::Info };
+            snippet = snippet.annotation(
+                span_level
+                    .span(offset..offset.saturating_add(1))
+                    .label(label),
+            );
+        }
 
-    // Trim leading empty lines.
-    while end_index > line {
-        if !lines[end_index.saturating_sub(1)].trim().is_empty() {
-            break;
+        let message_line = format!(
+            "{}:{}:{}: {} {}",
+            path,
+            primary.0,
+            primary.1,
+            code,
+            self.violation.message()
+        );
+        let mut report = level.title(&message_line).snippet(snippet);
+        for note in self.violation.notes() {
+            report = report.footer(Level::Note.title(note));
         }
-        end_index = end_index.saturating_sub(1);
-    }
-
-    let content_slice = lines[start_index.saturating_sub(1)..end_index]
-        .iter()
-        .fold(String::default(), |acc, line| format!("{acc}{line}\n"));
-
-    // Annotations are done by offset, so we need to count line
-    // lengths... including the newline character, which doesn't
-    // appear in `lines`!
-    let offset_up_to_line = lines[start_index.saturating_sub(1)..line.saturating_sub(1)]
-        .iter()
-        .fold(0, |acc, line| acc + line.chars().count() + 1);
-
-    // Something really weird going on here, where I can't get it to
-    // put the annotation in the first column: it's either in column 2
-    // or the end of the previous line. But does appear to be right
-    // for other columns!
-    let label_offset = offset_up_to_line + col.saturating_sub(1);
-
-    // Some annoyance here: we *have* to have some level prefix to our
-    // message. Might be fixed in future version of annotate-snippets
-    // -- or we use an earlier version with more control.
-    // Also, we could use `.origin(path)` to get the filename and
-    // line:col automatically, but see above about off-by-one error
-    let message_line = format!("{}:{}:{}: {} {}", path, line, col, code, message);
-    let snippet = Level::Warning.title(&message_line).snippet(
-        Snippet::source(&content_slice)
-            .line_start(start_index)
-            .annotation(
-                Level::Error
-                    .span(label_offset..label_offset.saturating_add(1))
-                    .label(code),
-            ),
-    );
-
-    let renderer = Renderer::styled();
-    let source_block = renderer.render(snippet);
-    writeln!(f, "{}", source_block)
+
+        let renderer = if color {
+            Renderer::styled()
+        } else {
+            Renderer::plain()
+        };
+        Ok(format!("{}\n", renderer.render(report)))
+    }
+}
+
+/// Map a [`Severity`] onto the `annotate_snippets` level used to render it.
+fn annotate_level(severity: Severity) -> Level {
+    match severity {
+        Severity::Info => Level::Info,
+        Severity::Warning => Level::Warning,
+        Severity::Error => Level::Error,
+    }
 }
 
 #[cfg(test)]
@@ -369,4 +637,17 @@ mod tests {
     }
 
     // TODO Test diagnostics
+
+    #[test]
+    fn test_violation_position_round_trips_through_json() {
+        for position in [
+            ViolationPosition::None,
+            ViolationPosition::Line(12),
+            ViolationPosition::LineCol((12, 4)),
+        ] {
+            let json = serde_json::to_string(&position).unwrap();
+            let round_tripped: ViolationPosition = serde_json::from_str(&json).unwrap();
+            assert_eq!(position, round_tripped);
+        }
+    }
 }
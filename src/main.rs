@@ -5,6 +5,8 @@ mod check;
 mod cli;
 mod code_errors;
 mod core;
+mod explain;
+mod files;
 mod parser;
 mod rules;
 mod settings;
@@ -12,6 +14,7 @@ mod test_utils;
 
 use check::check;
 use cli::{parse_args, SubCommands};
+use explain::explain;
 
 fn main() {
     let args = parse_args();
@@ -19,8 +22,8 @@ fn main() {
         SubCommands::Check(args) => {
             std::process::exit(check(args));
         }
-        _ => {
-            panic!("Not yet implemented!")
+        SubCommands::Explain(args) => {
+            std::process::exit(explain(args));
         }
     }
 }
@@ -0,0 +1,95 @@
+//! The rule registry: every [`Rule`](crate::Rule) implementation, grouped by
+//! the [`Category`](crate::Category) it belongs to.
+
+use crate::settings::Settings;
+use crate::{violation, Category, Code, Fix, Method, Rule, Violation, ViolationPosition};
+
+/// Flag lines with trailing whitespace.
+pub struct TrailingWhitespace;
+
+impl Rule for TrailingWhitespace {
+    fn title(&self) -> &str {
+        "trailing-whitespace"
+    }
+
+    fn method(&self) -> Method {
+        Method::Text(|source, _settings| {
+            let mut violations = Vec::new();
+            let mut byte_offset = 0;
+            for (i, line) in source.split_inclusive('\n').enumerate() {
+                let content = line.trim_end_matches(['\n', '\r']);
+                let trimmed = content.trim_end_matches([' ', '\t']);
+                if trimmed.len() < content.len() {
+                    let start = byte_offset + trimmed.len();
+                    let end = byte_offset + content.len();
+                    let fix = Fix::single(start, end, "");
+                    violations.push(
+                        Violation::new("Trailing whitespace", ViolationPosition::Line(i + 1))
+                            .with_fix(fix),
+                    );
+                }
+                byte_offset += line.len();
+            }
+            violations
+        })
+    }
+
+    fn explain(&self) -> &str {
+        "Trailing whitespace has no effect on compiled code, but it creates noisy diffs \
+         and is rejected by some compilers in fixed-form source."
+    }
+
+    fn entrypoints(&self) -> Vec<&str> {
+        vec!["TEXT"]
+    }
+
+    fn fixable(&self) -> bool {
+        true
+    }
+}
+
+/// Flag lines that exceed the configured maximum length.
+pub struct LineTooLong;
+
+impl Rule for LineTooLong {
+    fn title(&self) -> &str {
+        "line-too-long"
+    }
+
+    fn method(&self) -> Method {
+        Method::Text(|source, settings| {
+            source
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| line.len() > settings.line_length)
+                .map(|(i, line)| {
+                    violation!(
+                        format!(
+                            "Line length of {}, exceeds maximum of {}",
+                            line.len(),
+                            settings.line_length
+                        ),
+                        i + 1
+                    )
+                })
+                .collect()
+        })
+    }
+
+    fn explain(&self) -> &str {
+        "Overly long lines are hard to read in split views and can be truncated by fixed-form \
+         Fortran compilers."
+    }
+
+    fn entrypoints(&self) -> Vec<&str> {
+        vec!["TEXT"]
+    }
+}
+
+/// Every rule known to fortitude, paired with the [`Code`] it is registered under.
+pub fn full_ruleset() -> Vec<(Code, Box<dyn Rule>)> {
+    vec![
+        (Code::new(Category::Style, 1), Box::new(TrailingWhitespace)),
+        (Code::new(Category::Style, 2), Box::new(LineTooLong)),
+    ]
+}
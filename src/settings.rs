@@ -0,0 +1,31 @@
+//! User-facing configuration that influences how rules are applied and
+//! how results are reported.
+
+use std::collections::HashMap;
+
+use crate::{Code, Severity};
+
+/// The default maximum line length, used absent an explicit override.
+pub const DEFAULT_LINE_LENGTH: usize = 100;
+
+/// Settings that are threaded through to every [`crate::Method::Text`] rule,
+/// and consulted by the checking pipeline itself.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// Maximum permitted line length, used by style rules such as line-too-long.
+    pub line_length: usize,
+    /// Per-rule severity overrides, e.g. to promote a code to `Error` or demote
+    /// it to `Info`. Rules not present here fall back to their own
+    /// [`crate::Rule::severity`], and ultimately to
+    /// [`Severity::default_for_category`].
+    pub severity_overrides: HashMap<Code, Severity>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            line_length: DEFAULT_LINE_LENGTH,
+            severity_overrides: HashMap::new(),
+        }
+    }
+}